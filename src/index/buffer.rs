@@ -0,0 +1,688 @@
+use std::fmt;
+use std::error::Error;
+use std::ptr;
+
+use backend::Facade;
+use buffer::{Buffer, BufferAny, BufferSlice, BufferAnySlice, BufferType, BufferCreationError};
+
+use CapabilitiesSource;
+use GlObject;
+use ToGlEnum;
+use context::CommandContext;
+use gl;
+
+use index::{Index, IndexType, IndicesSource, PrimitiveType};
+use index::{is_primitive_restart_supported, is_fixed_index_primitive_restart_supported};
+use index::is_shader_storage_buffer_supported;
+use index::draw_with_primitive_restart;
+
+/// Returns whether `index` fits within the range `ty` can represent as a regular index (i.e. is
+/// not greater than `ty`'s own fixed restart sentinel).
+///
+/// Pure boundary check, factored out of `with_primitive_restart_index` so it can be unit-tested
+/// without needing a `Facade` to construct a buffer against.
+fn fits_restart_index(index: u32, ty: IndexType) -> bool {
+    index <= ty.fixed_restart_index()
+}
+
+/// Narrows a `u32` restart index down to what `u16` can represent, remapping the `u32` fixed
+/// restart sentinel (`0xFFFFFFFF`) to the `u16` one (`0xFFFF`) rather than truncating it, so
+/// restart semantics survive the conversion.
+///
+/// Fails with `IntoSupportedError::IndexOutOfRange` if `index` doesn't fit otherwise. Factored
+/// out of `IndexBuffer::<u32>::into_supported` so it can be unit-tested without needing a
+/// `Facade`.
+fn narrow_restart_index_to_u16(index: u32) -> Result<u32, IntoSupportedError> {
+    if index == IndexType::U32.fixed_restart_index() {
+        Ok(IndexType::U16.fixed_restart_index())
+    } else if index > IndexType::U16.fixed_restart_index() {
+        Err(IntoSupportedError::IndexOutOfRange(index))
+    } else {
+        Ok(index)
+    }
+}
+
+/// Narrows a single `u32` index value down to `u16`, failing with
+/// `IntoSupportedError::IndexOutOfRange` if it doesn't fit.
+///
+/// Factored out of `IndexBuffer::<u32>::into_supported` so it can be unit-tested without needing
+/// a `Facade`.
+fn narrow_index_to_u16(index: u32) -> Result<u16, IntoSupportedError> {
+    if index > IndexType::U16.fixed_restart_index() {
+        Err(IntoSupportedError::IndexOutOfRange(index))
+    } else {
+        Ok(index as u16)
+    }
+}
+
+/// A list of indices loaded in the graphics card's memory.
+#[derive(Debug)]
+pub struct IndexBuffer<T> where T: Index {
+    buffer: Buffer<[T]>,
+    primitives: PrimitiveType,
+    primitive_restart_index: Option<u32>,
+}
+
+impl<T> IndexBuffer<T> where T: Index {
+    /// Builds a new index buffer from a list of indices.
+    pub fn new<F>(facade: &F, prim: PrimitiveType, data: &[T])
+                  -> Result<IndexBuffer<T>, CreationError> where F: Facade
+    {
+        if !T::is_supported(facade) {
+            return Err(CreationError::IndexTypeNotSupported);
+        }
+
+        if !prim.is_supported(facade) {
+            return Err(CreationError::PrimitiveTypeNotSupported);
+        }
+
+        Ok(IndexBuffer {
+            buffer: Buffer::new(facade, data, BufferType::ElementArrayBuffer, false).unwrap(),
+            primitives: prim,
+            primitive_restart_index: None,
+        })
+    }
+
+    /// Configures the index value that should restart the current strip or fan, instead of
+    /// being treated as a regular vertex index.
+    ///
+    /// Returns `None` if `index` is greater than the index type's all-ones value, if `index` is
+    /// not that all-ones value and classic primitive restart isn't supported, or if the backend
+    /// supports neither classic primitive restart nor `GL_PRIMITIVE_RESTART_FIXED_INDEX`,
+    /// mirroring `IndexType::is_supported`.
+    pub fn with_primitive_restart_index<I>(mut self, index: I) -> Option<IndexBuffer<T>>
+        where I: Into<u32>
+    {
+        let index = index.into();
+
+        if !fits_restart_index(index, T::get_type()) {
+            return None;
+        }
+
+        if index != T::get_type().fixed_restart_index() && !is_primitive_restart_supported(&self.buffer) {
+            return None;
+        }
+
+        if index == T::get_type().fixed_restart_index() &&
+           !is_primitive_restart_supported(&self.buffer) &&
+           !is_fixed_index_primitive_restart_supported(&self.buffer)
+        {
+            return None;
+        }
+
+        self.primitive_restart_index = Some(index);
+        Some(self)
+    }
+
+    /// Returns the primitive restart index configured on this buffer, if any.
+    #[inline]
+    pub fn get_primitive_restart_index(&self) -> Option<u32> {
+        self.primitive_restart_index
+    }
+
+    /// Returns the type of primitives contained in this index buffer.
+    #[inline]
+    pub fn get_primitives_type(&self) -> PrimitiveType {
+        self.primitives
+    }
+
+    /// Returns the type of indices contained in this index buffer.
+    #[inline]
+    pub fn get_indices_type(&self) -> IndexType {
+        T::get_type()
+    }
+
+    /// Returns the number of indices in this index buffer.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Builds a slice-view of this index buffer.
+    #[inline]
+    pub fn as_slice(&self) -> IndexBufferSlice<T> {
+        IndexBufferSlice {
+            buffer: self.buffer.as_slice(),
+            primitives: self.primitives,
+            primitive_restart_index: self.primitive_restart_index,
+        }
+    }
+
+    /// Binds this index buffer's underlying buffer as a shader storage buffer, at `binding`, so
+    /// that a compute shader can generate or cull the indices it contains before it is consumed
+    /// by a draw call.
+    ///
+    /// Returns `false` without binding anything if the backend doesn't support
+    /// `GL_ARB_shader_storage_buffer_object`.
+    ///
+    /// Call `index::insert_element_array_barrier` between the compute dispatch that writes this
+    /// buffer and the draw call that reads it, so the draw is guaranteed to see the writes.
+    pub unsafe fn bind_as_ssbo(&self, ctxt: &mut CommandContext, binding: gl::types::GLuint) -> bool {
+        if !is_shader_storage_buffer_supported(ctxt) {
+            return false;
+        }
+
+        ctxt.gl.BindBufferBase(gl::SHADER_STORAGE_BUFFER, binding, self.buffer.get_id());
+        true
+    }
+}
+
+impl<'a, T> From<&'a IndexBuffer<T>> for IndicesSource<'a> where T: Index {
+    #[inline]
+    fn from(buf: &'a IndexBuffer<T>) -> IndicesSource<'a> {
+        From::from(buf.as_slice())
+    }
+}
+
+impl IndexBuffer<u8> {
+    /// Converts this buffer into a `u16`-indexed buffer.
+    ///
+    /// Some drivers handle `u8` indices poorly even though `IndexType::U8::is_supported` always
+    /// returns `true`. This widens every index to `u16`, which is always lossless, and remaps
+    /// the primitive restart index (if any) from `0xFF` to `0xFFFF` rather than casting it
+    /// numerically, so restart semantics are preserved.
+    pub fn into_supported<F>(self, facade: &F) -> Result<IndexBuffer<u16>, CreationError>
+        where F: Facade
+    {
+        let primitives = self.primitives;
+        let restart_index = self.primitive_restart_index;
+        let remap_restart = restart_index == Some(IndexType::U8.fixed_restart_index());
+        let data: Vec<u16> = self.buffer.read().into_iter().map(|index| {
+            if remap_restart && index as u32 == IndexType::U8.fixed_restart_index() {
+                IndexType::U16.fixed_restart_index() as u16
+            } else {
+                index as u16
+            }
+        }).collect();
+
+        let buffer = IndexBuffer::new(facade, primitives, &data)?;
+
+        Ok(match restart_index {
+            Some(index) => {
+                let index = if index == IndexType::U8.fixed_restart_index() {
+                    IndexType::U16.fixed_restart_index()
+                } else {
+                    index
+                };
+
+                buffer.with_primitive_restart_index(index)
+                    .expect("widening u8 to u16 cannot make primitive restart unsupported")
+            },
+            None => buffer,
+        })
+    }
+}
+
+impl IndexBuffer<u32> {
+    /// Converts this buffer into a narrower index type, for backends such as GLES2 that cannot
+    /// draw with `u32` indices.
+    ///
+    /// If the backend already supports `u32` indices, this is a cheap no-op that just returns
+    /// `self` with its type erased; narrowing unconditionally would regress a mesh with more
+    /// than 65535 vertices that would otherwise have drawn fine as `u32`.
+    ///
+    /// Otherwise, narrows to `u16`, failing with `IntoSupportedError::IndexOutOfRange` if any
+    /// index, or the configured primitive restart index, does not fit. The restart index (if
+    /// any) is remapped from `0xFFFFFFFF` to `0xFFFF` rather than cast numerically, so restart
+    /// semantics are preserved.
+    pub fn into_supported<F>(self, facade: &F) -> Result<IndexBufferAny, IntoSupportedError>
+        where F: Facade
+    {
+        if IndexType::U32.is_supported(facade) {
+            return Ok(self.into());
+        }
+
+        let primitives = self.primitives;
+
+        let restart_index = match self.primitive_restart_index {
+            Some(index) => Some(narrow_restart_index_to_u16(index)?),
+            None => None,
+        };
+
+        let remap_restart = self.primitive_restart_index == Some(IndexType::U32.fixed_restart_index());
+        let mut data = Vec::with_capacity(self.buffer.len());
+        for index in self.buffer.read() {
+            if remap_restart && index == IndexType::U32.fixed_restart_index() {
+                data.push(IndexType::U16.fixed_restart_index() as u16);
+            } else {
+                data.push(narrow_index_to_u16(index)?);
+            }
+        }
+
+        let buffer = IndexBuffer::new(facade, primitives, &data)?;
+
+        let buffer = match restart_index {
+            Some(index) => {
+                buffer.with_primitive_restart_index(index)
+                    .expect("a validated u32-to-u16 conversion cannot make primitive restart unsupported")
+            },
+            None => buffer,
+        };
+
+        Ok(buffer.into())
+    }
+}
+
+/// Error that can happen while converting an `IndexBuffer` to a narrower `IndexType`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IntoSupportedError {
+    /// One of the indices does not fit in the destination `IndexType`.
+    IndexOutOfRange(u32),
+
+    /// An error happened while creating the converted buffer.
+    CreationError(CreationError),
+}
+
+impl From<CreationError> for IntoSupportedError {
+    #[inline]
+    fn from(err: CreationError) -> IntoSupportedError {
+        IntoSupportedError::CreationError(err)
+    }
+}
+
+impl fmt::Display for IntoSupportedError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", self.description())
+    }
+}
+
+impl Error for IntoSupportedError {
+    fn description(&self) -> &str {
+        match self {
+            &IntoSupportedError::IndexOutOfRange(_) => {
+                "One of the indices does not fit in the destination index type"
+            },
+            &IntoSupportedError::CreationError(_) => {
+                "An error happened while creating the converted buffer"
+            },
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match self {
+            &IntoSupportedError::CreationError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// A borrowed slice of an `IndexBuffer`.
+#[derive(Copy, Clone, Debug)]
+pub struct IndexBufferSlice<'a, T: 'a> where T: Index {
+    buffer: BufferSlice<'a, [T]>,
+    primitives: PrimitiveType,
+    primitive_restart_index: Option<u32>,
+}
+
+impl<'a, T> IndexBufferSlice<'a, T> where T: Index {
+    /// Overrides the primitive restart index carried by this slice.
+    ///
+    /// See `IndexBuffer::with_primitive_restart_index`.
+    pub fn with_primitive_restart_index<I>(mut self, index: I) -> Option<IndexBufferSlice<'a, T>>
+        where I: Into<u32>
+    {
+        let index = index.into();
+
+        if !fits_restart_index(index, T::get_type()) {
+            return None;
+        }
+
+        if index != T::get_type().fixed_restart_index() &&
+           !is_primitive_restart_supported(&self.buffer)
+        {
+            return None;
+        }
+
+        if index == T::get_type().fixed_restart_index() &&
+           !is_primitive_restart_supported(&self.buffer) &&
+           !is_fixed_index_primitive_restart_supported(&self.buffer)
+        {
+            return None;
+        }
+
+        self.primitive_restart_index = Some(index);
+        Some(self)
+    }
+
+    /// Returns the primitive restart index configured on this slice, if any.
+    #[inline]
+    pub fn get_primitive_restart_index(&self) -> Option<u32> {
+        self.primitive_restart_index
+    }
+
+    /// Returns the type of primitives contained in this index buffer.
+    #[inline]
+    pub fn get_primitives_type(&self) -> PrimitiveType {
+        self.primitives
+    }
+
+    /// Returns the type of indices contained in this index buffer.
+    #[inline]
+    pub fn get_indices_type(&self) -> IndexType {
+        T::get_type()
+    }
+
+    /// Returns the number of indices in this slice.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Binds this slice as a shader storage buffer.
+    ///
+    /// Unlike `IndexBuffer::bind_as_ssbo`, this binds only the byte range covered by the slice
+    /// (via `glBindBufferRange`) rather than the whole underlying buffer object, so a compute
+    /// shader can't read or write past the slice's own bounds.
+    ///
+    /// See `IndexBuffer::bind_as_ssbo`.
+    pub unsafe fn bind_as_ssbo(&self, ctxt: &mut CommandContext, binding: gl::types::GLuint) -> bool {
+        if !is_shader_storage_buffer_supported(ctxt) {
+            return false;
+        }
+
+        ctxt.gl.BindBufferRange(gl::SHADER_STORAGE_BUFFER, binding, self.buffer.get_id(),
+                                 self.buffer.get_offset_bytes() as gl::types::GLintptr,
+                                 self.buffer.get_size() as gl::types::GLsizeiptr);
+        true
+    }
+
+    /// Returns the underlying buffer, with its type erased.
+    #[inline]
+    pub fn as_buffer_any_slice(&self) -> BufferAnySlice<'a> {
+        self.buffer.into()
+    }
+}
+
+impl<'a> IndexBufferSlice<'a, u8> {
+    /// Converts this slice into an owned `u16`-indexed buffer.
+    ///
+    /// See `IndexBuffer::<u8>::into_supported`.
+    pub fn into_supported<F>(self, facade: &F) -> Result<IndexBuffer<u16>, CreationError>
+        where F: Facade
+    {
+        let primitives = self.primitives;
+        let restart_index = self.primitive_restart_index;
+        let remap_restart = restart_index == Some(IndexType::U8.fixed_restart_index());
+        let data: Vec<u16> = self.buffer.read().into_iter().map(|index| {
+            if remap_restart && index as u32 == IndexType::U8.fixed_restart_index() {
+                IndexType::U16.fixed_restart_index() as u16
+            } else {
+                index as u16
+            }
+        }).collect();
+
+        let buffer = IndexBuffer::new(facade, primitives, &data)?;
+
+        Ok(match restart_index {
+            Some(index) => {
+                let index = if index == IndexType::U8.fixed_restart_index() {
+                    IndexType::U16.fixed_restart_index()
+                } else {
+                    index
+                };
+
+                buffer.with_primitive_restart_index(index)
+                    .expect("widening u8 to u16 cannot make primitive restart unsupported")
+            },
+            None => buffer,
+        })
+    }
+}
+
+impl<'a> IndexBufferSlice<'a, u32> {
+    /// Converts this slice into an owned index buffer, narrowing to `u16` only if the backend
+    /// doesn't support `u32` indices.
+    ///
+    /// See `IndexBuffer::<u32>::into_supported`.
+    pub fn into_supported<F>(self, facade: &F) -> Result<IndexBufferAny, IntoSupportedError>
+        where F: Facade
+    {
+        let primitives = self.primitives;
+
+        if IndexType::U32.is_supported(facade) {
+            let data: Vec<u32> = self.buffer.read();
+            let buffer = IndexBuffer::new(facade, primitives, &data)?;
+
+            let buffer = match self.primitive_restart_index {
+                Some(index) => {
+                    buffer.with_primitive_restart_index(index)
+                        .expect("index was already validated against u32 when set on this slice")
+                },
+                None => buffer,
+            };
+
+            return Ok(buffer.into());
+        }
+
+        let restart_index = match self.primitive_restart_index {
+            Some(index) => Some(narrow_restart_index_to_u16(index)?),
+            None => None,
+        };
+
+        let remap_restart = self.primitive_restart_index == Some(IndexType::U32.fixed_restart_index());
+        let mut data = Vec::with_capacity(self.buffer.len());
+        for index in self.buffer.read() {
+            if remap_restart && index == IndexType::U32.fixed_restart_index() {
+                data.push(IndexType::U16.fixed_restart_index() as u16);
+            } else {
+                data.push(narrow_index_to_u16(index)?);
+            }
+        }
+
+        let buffer = IndexBuffer::new(facade, primitives, &data)?;
+
+        let buffer = match restart_index {
+            Some(index) => {
+                buffer.with_primitive_restart_index(index)
+                    .expect("a validated u32-to-u16 conversion cannot make primitive restart unsupported")
+            },
+            None => buffer,
+        };
+
+        Ok(buffer.into())
+    }
+}
+
+impl<'a, T> From<IndexBufferSlice<'a, T>> for IndicesSource<'a> where T: Index {
+    #[inline]
+    fn from(source: IndexBufferSlice<'a, T>) -> IndicesSource<'a> {
+        IndicesSource::IndexBuffer {
+            buffer: source.buffer.into(),
+            data_type: T::get_type(),
+            primitives: source.primitives,
+            primitive_restart_index: source.primitive_restart_index,
+        }
+    }
+}
+
+/// A type-erased `IndexBuffer`.
+#[derive(Debug)]
+pub struct IndexBufferAny {
+    buffer: BufferAny,
+    data_type: IndexType,
+    primitives: PrimitiveType,
+    primitive_restart_index: Option<u32>,
+}
+
+impl IndexBufferAny {
+    /// Returns the type of primitives contained in this index buffer.
+    #[inline]
+    pub fn get_primitives_type(&self) -> PrimitiveType {
+        self.primitives
+    }
+
+    /// Returns the type of indices contained in this index buffer.
+    #[inline]
+    pub fn get_indices_type(&self) -> IndexType {
+        self.data_type
+    }
+
+    /// Returns the primitive restart index configured on this buffer, if any.
+    #[inline]
+    pub fn get_primitive_restart_index(&self) -> Option<u32> {
+        self.primitive_restart_index
+    }
+
+    /// Returns the number of indices in this index buffer.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buffer.get_size() / self.data_type.get_size()
+    }
+
+    /// Issues the `glDrawElements`/`glDrawElementsInstanced` call for this index buffer,
+    /// enabling primitive restart around it if a restart index is configured (see
+    /// `IndexBuffer::with_primitive_restart_index`).
+    ///
+    /// The buffer must already be bound as the current `GL_ELEMENT_ARRAY_BUFFER`; setting up
+    /// the rest of the draw state (vertex buffers, program, uniforms) is the caller's
+    /// responsibility.
+    pub unsafe fn draw(&self, ctxt: &mut CommandContext, instance_count: Option<u32>) {
+        let source = IndicesSource::from(self);
+        let primitives = self.primitives;
+        let data_type = self.data_type;
+        let count = self.len() as gl::types::GLsizei;
+
+        draw_with_primitive_restart(ctxt, &source, |ctxt| {
+            match instance_count {
+                Some(instances) => {
+                    ctxt.gl.DrawElementsInstanced(primitives.to_glenum(), count,
+                                                   data_type.to_glenum(), ptr::null(),
+                                                   instances as gl::types::GLsizei);
+                },
+                None => {
+                    ctxt.gl.DrawElements(primitives.to_glenum(), count, data_type.to_glenum(),
+                                          ptr::null());
+                },
+            }
+        });
+    }
+
+    /// Binds this index buffer as a shader storage buffer.
+    ///
+    /// See `IndexBuffer::bind_as_ssbo`.
+    pub unsafe fn bind_as_ssbo(&self, ctxt: &mut CommandContext, binding: gl::types::GLuint) -> bool {
+        if !is_shader_storage_buffer_supported(ctxt) {
+            return false;
+        }
+
+        ctxt.gl.BindBufferBase(gl::SHADER_STORAGE_BUFFER, binding, self.buffer.get_id());
+        true
+    }
+}
+
+impl<'a> From<&'a IndexBufferAny> for IndicesSource<'a> {
+    #[inline]
+    fn from(buf: &'a IndexBufferAny) -> IndicesSource<'a> {
+        IndicesSource::IndexBuffer {
+            buffer: BufferAnySlice::from(&buf.buffer),
+            data_type: buf.data_type,
+            primitives: buf.primitives,
+            primitive_restart_index: buf.primitive_restart_index,
+        }
+    }
+}
+
+impl<T> From<IndexBuffer<T>> for IndexBufferAny where T: Index {
+    #[inline]
+    fn from(buf: IndexBuffer<T>) -> IndexBufferAny {
+        IndexBufferAny {
+            buffer: buf.buffer.into(),
+            data_type: T::get_type(),
+            primitives: buf.primitives,
+            primitive_restart_index: buf.primitive_restart_index,
+        }
+    }
+}
+
+/// Error that can happen while creating an index buffer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CreationError {
+    /// The type of the indices is not supported by the backend.
+    IndexTypeNotSupported,
+
+    /// The type of the primitives is not supported by the backend.
+    PrimitiveTypeNotSupported,
+
+    /// An error happened while creating the buffer.
+    BufferCreationError(BufferCreationError),
+}
+
+impl From<BufferCreationError> for CreationError {
+    #[inline]
+    fn from(err: BufferCreationError) -> CreationError {
+        CreationError::BufferCreationError(err)
+    }
+}
+
+impl fmt::Display for CreationError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", self.description())
+    }
+}
+
+impl Error for CreationError {
+    fn description(&self) -> &str {
+        match self {
+            &CreationError::IndexTypeNotSupported => {
+                "The type of the indices is not supported by the backend"
+            },
+            &CreationError::PrimitiveTypeNotSupported => {
+                "The type of the primitives is not supported by the backend"
+            },
+            &CreationError::BufferCreationError(_) => {
+                "An error happened while creating the buffer"
+            },
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match self {
+            &CreationError::BufferCreationError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fits_restart_index, narrow_index_to_u16, narrow_restart_index_to_u16};
+    use super::IntoSupportedError;
+    use index::IndexType;
+
+    #[test]
+    fn fits_restart_index_accepts_at_boundary() {
+        assert!(fits_restart_index(0xFFFF, IndexType::U16));
+        assert!(fits_restart_index(0xFFFFFFFF, IndexType::U32));
+    }
+
+    #[test]
+    fn fits_restart_index_rejects_over_boundary() {
+        assert!(!fits_restart_index(0x10000, IndexType::U16));
+    }
+
+    #[test]
+    fn narrow_index_to_u16_accepts_at_boundary() {
+        assert_eq!(narrow_index_to_u16(0xFFFF), Ok(0xFFFFu16));
+    }
+
+    #[test]
+    fn narrow_index_to_u16_rejects_over_boundary() {
+        assert_eq!(narrow_index_to_u16(0x10000), Err(IntoSupportedError::IndexOutOfRange(0x10000)));
+    }
+
+    #[test]
+    fn narrow_restart_index_remaps_u32_sentinel_to_u16_sentinel() {
+        assert_eq!(narrow_restart_index_to_u16(0xFFFFFFFF), Ok(0xFFFFu32));
+    }
+
+    #[test]
+    fn narrow_restart_index_passes_through_values_that_already_fit() {
+        assert_eq!(narrow_restart_index_to_u16(42), Ok(42));
+    }
+
+    #[test]
+    fn narrow_restart_index_rejects_out_of_range_value() {
+        assert_eq!(narrow_restart_index_to_u16(0xFFFF0000),
+                   Err(IntoSupportedError::IndexOutOfRange(0xFFFF0000)));
+    }
+}
@@ -32,6 +32,7 @@ The idea is to put a list of things to render in a buffer, and pass that buffer
 use gl;
 use ToGlEnum;
 use CapabilitiesSource;
+use context::CommandContext;
 use version::Api;
 use version::Version;
 
@@ -58,6 +59,11 @@ pub enum IndicesSource<'a> {
         data_type: IndexType,
         /// Type of primitives contained in the vertex source.
         primitives: PrimitiveType,
+        /// If set, the index value that should restart the current strip or fan instead of
+        /// being treated as a regular vertex index.
+        ///
+        /// Only meaningful when `primitives` is a strip or fan type.
+        primitive_restart_index: Option<u32>,
     },
 
     /// Use a multidraw indirect buffer without indices.
@@ -99,6 +105,62 @@ impl<'a> IndicesSource<'a> {
             &IndicesSource::NoIndices { primitives } => primitives,
         }
     }
+
+    /// Returns the primitive restart index to use for this source, if any.
+    #[inline]
+    pub fn get_primitive_restart_index(&self) -> Option<u32> {
+        match self {
+            &IndicesSource::IndexBuffer { primitive_restart_index, .. } => primitive_restart_index,
+            _ => None,
+        }
+    }
+
+    /// Returns the type of indices used by this source, if it carries one.
+    #[inline]
+    pub fn get_index_type(&self) -> Option<IndexType> {
+        match self {
+            &IndicesSource::IndexBuffer { data_type, .. } => Some(data_type),
+            &IndicesSource::MultidrawElement { data_type, .. } => Some(data_type),
+            _ => None,
+        }
+    }
+}
+
+/// Enables the primitive restart mode required by `source` (classic `glPrimitiveRestartIndex`,
+/// or `GL_PRIMITIVE_RESTART_FIXED_INDEX` when the configured index is the type's all-ones
+/// sentinel and the backend supports it), runs `draw_elements`, then disables it again.
+///
+/// This is what the draw path must go through instead of issuing `glDrawElements*` directly, so
+/// that `source.get_primitive_restart_index()` actually takes effect. If `source` has no
+/// restart index configured, or the backend supports neither restart mode, this is equivalent
+/// to calling `draw_elements` directly.
+pub unsafe fn draw_with_primitive_restart<F>(ctxt: &mut CommandContext, source: &IndicesSource,
+                                              draw_elements: F)
+    where F: FnOnce(&mut CommandContext)
+{
+    let enabled_mode = source.get_primitive_restart_index().and_then(|index| {
+        let data_type = source.get_index_type()
+            .expect("a primitive restart index is only ever set alongside an index buffer");
+
+        if index == data_type.fixed_restart_index() &&
+           is_fixed_index_primitive_restart_supported(ctxt)
+        {
+            ctxt.gl.Enable(gl::PRIMITIVE_RESTART_FIXED_INDEX);
+            Some(gl::PRIMITIVE_RESTART_FIXED_INDEX)
+        } else if is_primitive_restart_supported(ctxt) {
+            ctxt.gl.Enable(gl::PRIMITIVE_RESTART);
+            ctxt.gl.PrimitiveRestartIndex(index);
+            Some(gl::PRIMITIVE_RESTART)
+        } else {
+            None
+        }
+    });
+
+    draw_elements(ctxt);
+
+    if let Some(mode) = enabled_mode {
+        ctxt.gl.Disable(mode);
+    }
 }
 
 /// List of available primitives.
@@ -237,6 +299,57 @@ impl IndexType {
             },
         }
     }
+
+    /// Returns the all-ones value of this index type, i.e. the value used as the implicit
+    /// restart index when `GL_PRIMITIVE_RESTART_FIXED_INDEX` is enabled.
+    #[inline]
+    pub fn fixed_restart_index(&self) -> u32 {
+        match self {
+            &IndexType::U8 => 0xFF,
+            &IndexType::U16 => 0xFFFF,
+            &IndexType::U32 => 0xFFFFFFFF,
+        }
+    }
+}
+
+/// Returns true if the backend can restart a strip or fan on a specific, arbitrary index value
+/// (as opposed to only the type's all-ones value).
+///
+/// This corresponds to the classic `glPrimitiveRestartIndex` functionality, available since
+/// OpenGL 3.1 or through the `GL_ARB_primitive_restart` extension.
+#[inline]
+pub fn is_primitive_restart_supported<C>(caps: &C) -> bool where C: CapabilitiesSource {
+    caps.get_version() >= &Version(Api::Gl, 3, 1) ||
+    caps.get_extensions().gl_arb_primitive_restart
+}
+
+/// Returns true if the backend supports `GL_PRIMITIVE_RESTART_FIXED_INDEX`, which always
+/// restarts on the index type's all-ones value without any extra per-draw state.
+///
+/// Available since OpenGL 4.3 or GLES 3.0.
+#[inline]
+pub fn is_fixed_index_primitive_restart_supported<C>(caps: &C) -> bool where C: CapabilitiesSource {
+    caps.get_version() >= &Version(Api::Gl, 4, 3) ||
+    caps.get_version() >= &Version(Api::GlEs, 3, 0)
+}
+
+/// Returns true if an index buffer can be bound as a shader storage buffer, letting a compute
+/// shader generate or cull the indices it contains.
+///
+/// Available since OpenGL 4.3 or through the `GL_ARB_shader_storage_buffer_object` extension.
+#[inline]
+pub fn is_shader_storage_buffer_supported<C>(caps: &C) -> bool where C: CapabilitiesSource {
+    caps.get_version() >= &Version(Api::Gl, 4, 3) ||
+    caps.get_extensions().gl_arb_shader_storage_buffer_object
+}
+
+/// Inserts a `GL_ELEMENT_ARRAY_BARRIER_BIT` memory barrier, which must happen between a compute
+/// dispatch that writes an index buffer bound as a shader storage buffer (see
+/// `IndexBuffer::bind_as_ssbo`) and the draw call that reads it back as indices, or the draw is
+/// not guaranteed to see the writes.
+#[inline]
+pub unsafe fn insert_element_array_barrier(ctxt: &mut CommandContext) {
+    ctxt.gl.MemoryBarrier(gl::ELEMENT_ARRAY_BARRIER_BIT);
 }
 
 impl ToGlEnum for IndexType {
@@ -0,0 +1,242 @@
+use std::ptr;
+
+use backend::Facade;
+use buffer::{Buffer, BufferAnySlice, BufferType, BufferCreationError};
+
+use CapabilitiesSource;
+use GlObject;
+use ToGlEnum;
+use context::CommandContext;
+use gl;
+use version::{Api, Version};
+
+use index::{Index, IndexBuffer, IndexBufferSlice, IndicesSource, PrimitiveType};
+
+/// The layout of a single entry of a `DrawCommandsNoIndicesBuffer`, matching
+/// `GL_DRAW_INDIRECT_BUFFER`'s `DrawArraysIndirectCommand`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DrawCommandNoIndices {
+    /// Number of vertices to draw.
+    pub count: u32,
+    /// Number of instances to draw.
+    pub instance_count: u32,
+    /// First vertex to draw.
+    pub first: u32,
+    /// Base instance, added to the instance index for instanced attributes.
+    pub base_instance: u32,
+}
+
+/// The layout of a single entry of a `DrawCommandsIndicesBuffer`, matching
+/// `GL_DRAW_INDIRECT_BUFFER`'s `DrawElementsIndirectCommand`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DrawCommandIndices {
+    /// Number of indices to draw.
+    pub count: u32,
+    /// Number of instances to draw.
+    pub instance_count: u32,
+    /// Offset, in indices, of the first index to draw.
+    pub first_index: u32,
+    /// Value added to each index before indexing into the vertex buffers.
+    pub base_vertex: u32,
+    /// Base instance, added to the instance index for instanced attributes.
+    pub base_instance: u32,
+}
+
+/// A list of `DrawCommandNoIndices`, for use with multidraw indirect rendering without indices.
+pub struct DrawCommandsNoIndicesBuffer {
+    buffer: Buffer<[DrawCommandNoIndices]>,
+}
+
+impl DrawCommandsNoIndicesBuffer {
+    /// Builds a new buffer of commands, initialized from the CPU.
+    pub fn new<F>(facade: &F, data: &[DrawCommandNoIndices])
+                  -> Result<DrawCommandsNoIndicesBuffer, BufferCreationError> where F: Facade
+    {
+        Ok(DrawCommandsNoIndicesBuffer {
+            buffer: try!(Buffer::new(facade, data, BufferType::DrawIndirectBuffer, false)),
+        })
+    }
+
+    /// Returns the number of commands that this buffer can hold.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Binds this buffer as a shader storage buffer, at `binding`, so that a compute shader can
+    /// append draw commands (instance counts, first-vertex, base-instance) directly.
+    ///
+    /// Returns `false` without binding anything if the backend doesn't support
+    /// `GL_ARB_shader_storage_buffer_object`.
+    ///
+    /// Call `insert_command_barrier` between the compute dispatch that writes this buffer and
+    /// the draw call that reads it, so the draw is guaranteed to see the writes.
+    pub unsafe fn bind_as_ssbo(&self, ctxt: &mut CommandContext, binding: gl::types::GLuint) -> bool {
+        if !super::is_shader_storage_buffer_supported(ctxt) {
+            return false;
+        }
+
+        ctxt.gl.BindBufferBase(gl::SHADER_STORAGE_BUFFER, binding, self.buffer.get_id());
+        true
+    }
+
+    /// Builds an `IndicesSource` that draws `primitives` using every command in this buffer,
+    /// with the draw count supplied by the CPU.
+    #[inline]
+    pub fn with_primitive_type(&self, primitives: PrimitiveType) -> IndicesSource {
+        IndicesSource::MultidrawArray {
+            buffer: self.buffer.as_slice().into(),
+            primitives: primitives,
+        }
+    }
+}
+
+/// A list of `DrawCommandIndices`, for use with multidraw indirect rendering with indices.
+pub struct DrawCommandsIndicesBuffer {
+    buffer: Buffer<[DrawCommandIndices]>,
+}
+
+impl DrawCommandsIndicesBuffer {
+    /// Builds a new buffer of commands, initialized from the CPU.
+    pub fn new<F>(facade: &F, data: &[DrawCommandIndices])
+                  -> Result<DrawCommandsIndicesBuffer, BufferCreationError> where F: Facade
+    {
+        Ok(DrawCommandsIndicesBuffer {
+            buffer: try!(Buffer::new(facade, data, BufferType::DrawIndirectBuffer, false)),
+        })
+    }
+
+    /// Returns the number of commands that this buffer can hold.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Binds this buffer as a shader storage buffer, at `binding`, so that a compute shader can
+    /// append draw commands directly, typically alongside an atomic draw counter.
+    ///
+    /// Returns `false` without binding anything if the backend doesn't support
+    /// `GL_ARB_shader_storage_buffer_object`.
+    ///
+    /// Call `insert_command_barrier` between the compute dispatch that writes this buffer and
+    /// the draw call that reads it, so the draw is guaranteed to see the writes.
+    pub unsafe fn bind_as_ssbo(&self, ctxt: &mut CommandContext, binding: gl::types::GLuint) -> bool {
+        if !super::is_shader_storage_buffer_supported(ctxt) {
+            return false;
+        }
+
+        ctxt.gl.BindBufferBase(gl::SHADER_STORAGE_BUFFER, binding, self.buffer.get_id());
+        true
+    }
+
+    /// Builds an `IndicesSource` that draws every command in this buffer against `indices`,
+    /// with the draw count supplied by the CPU.
+    #[inline]
+    pub fn with_indices<'a, T>(&'a self, indices: &'a IndexBuffer<T>) -> IndicesSource<'a>
+        where T: Index
+    {
+        self.with_index_slice(indices.as_slice())
+    }
+
+    /// Builds an `IndicesSource` that draws every command in this buffer against `indices`,
+    /// with the draw count supplied by the CPU.
+    pub fn with_index_slice<'a, T>(&'a self, indices: IndexBufferSlice<'a, T>) -> IndicesSource<'a>
+        where T: Index
+    {
+        IndicesSource::MultidrawElement {
+            commands: self.buffer.as_slice().into(),
+            indices: indices.as_buffer_any_slice(),
+            data_type: indices.get_indices_type(),
+            primitives: indices.get_primitives_type(),
+        }
+    }
+
+    /// Builds an `IndirectCountDraw` that draws `indices`, reading the number of commands to
+    /// process from `count_buffer` on the GPU instead of a CPU-supplied count.
+    ///
+    /// This lets a compute pass append `DrawCommandIndices` entries and atomically increment a
+    /// draw count, with a single indirect draw consuming whatever the GPU produced. Returns
+    /// `None` if the backend doesn't support `glMultiDrawElementsIndirectCount`, i.e. neither
+    /// OpenGL 4.6 nor `GL_ARB_indirect_parameters` is available; callers should fall back to
+    /// `with_indices`/`with_index_slice` with a CPU-known count in that case.
+    pub fn with_indices_and_gpu_count<'a, T, C>(&'a self, indices: IndexBufferSlice<'a, T>,
+                                                 count_buffer: BufferAnySlice<'a>,
+                                                 count_buffer_offset: usize, max_draw_count: u32,
+                                                 caps: &C)
+                                                 -> Option<IndirectCountDraw<'a>>
+        where T: Index, C: CapabilitiesSource
+    {
+        if !is_indirect_parameters_supported(caps) {
+            return None;
+        }
+
+        Some(IndirectCountDraw {
+            source: self.with_index_slice(indices),
+            count_buffer: count_buffer,
+            count_buffer_offset: count_buffer_offset,
+            max_draw_count: max_draw_count,
+        })
+    }
+}
+
+/// Returns true if `glMultiDrawElementsIndirectCount`/`glMultiDrawArraysIndirectCount` are
+/// available, letting the draw count for a multidraw indirect call be read from a GPU buffer.
+///
+/// Available since OpenGL 4.6 or through the `GL_ARB_indirect_parameters` extension.
+#[inline]
+pub fn is_indirect_parameters_supported<C>(caps: &C) -> bool where C: CapabilitiesSource {
+    caps.get_version() >= &Version(Api::Gl, 4, 6) ||
+    caps.get_extensions().gl_arb_indirect_parameters
+}
+
+/// Inserts a `GL_COMMAND_BARRIER_BIT` memory barrier, which must happen between a compute
+/// dispatch that writes a commands (and/or draw count) buffer bound as a shader storage buffer
+/// (see `DrawCommandsIndicesBuffer::bind_as_ssbo`) and the indirect draw call that reads it
+/// back, or the draw is not guaranteed to see the writes.
+#[inline]
+pub unsafe fn insert_command_barrier(ctxt: &mut CommandContext) {
+    ctxt.gl.MemoryBarrier(gl::COMMAND_BARRIER_BIT);
+}
+
+/// A multidraw indirect call whose draw count is read from a GPU buffer rather than supplied
+/// by the CPU.
+///
+/// Insert a `GL_COMMAND_BARRIER_BIT` memory barrier between the compute dispatch that writes
+/// `count_buffer` (and the associated commands buffer) and the draw call that consumes it.
+pub struct IndirectCountDraw<'a> {
+    /// The underlying CPU-count indices source, reused for the commands/indices buffers.
+    pub source: IndicesSource<'a>,
+    /// The buffer holding the actual draw count, as a 4-byte unsigned integer.
+    pub count_buffer: BufferAnySlice<'a>,
+    /// Byte offset of the count within `count_buffer`.
+    pub count_buffer_offset: usize,
+    /// Upper bound on the number of commands that may be read, used to size driver-side work.
+    pub max_draw_count: u32,
+}
+
+impl<'a> IndirectCountDraw<'a> {
+    /// Issues `glMultiDrawElementsIndirectCount`, drawing every command the GPU produced in the
+    /// commands buffer wrapped by `self.source`, using `self.count_buffer` to supply the actual
+    /// number of commands to process instead of a CPU-known count.
+    ///
+    /// The commands buffer (bound as `GL_DRAW_INDIRECT_BUFFER`) and the index buffer must
+    /// already be set up by the caller, as for a regular indexed multidraw; this call only
+    /// additionally binds `self.count_buffer` as `GL_PARAMETER_BUFFER`. If the commands or count
+    /// were just written by a compute dispatch, call `insert_command_barrier` first.
+    pub unsafe fn draw(&self, ctxt: &mut CommandContext) {
+        let primitives = self.source.get_primitives_type();
+        let data_type = self.source.get_index_type()
+            .expect("IndirectCountDraw always wraps a MultidrawElement source");
+
+        ctxt.gl.BindBuffer(gl::PARAMETER_BUFFER, self.count_buffer.get_id());
+
+        ctxt.gl.MultiDrawElementsIndirectCount(primitives.to_glenum(), data_type.to_glenum(),
+                                                ptr::null(),
+                                                (self.count_buffer.get_offset_bytes() +
+                                                 self.count_buffer_offset) as gl::types::GLintptr,
+                                                self.max_draw_count as gl::types::GLsizei,
+                                                0);
+    }
+}
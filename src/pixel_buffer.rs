@@ -5,15 +5,17 @@ Contrary to textures, pixel buffers are stored in a client-defined format. They
 to transfer data to or from the video memory, before or after being turned into a texture.
  */
 use std::borrow::Cow;
+use std::cell::Cell;
 use std::marker::PhantomData;
 
 use backend::Facade;
 
-use texture::{RawImage2d, Texture2dDataSink, ClientFormat};
+use texture::{RawImage2d, Texture2dDataSink, Texture2dDataSource, ClientFormat};
 
+use CapabilitiesSource;
 use GlObject;
 use BufferViewExt;
-use buffer::{BufferView, BufferViewAny, BufferType};
+use buffer::{BufferView, BufferViewAny, BufferType, Fence};
 use gl;
 
 /// Buffer that stores the content of a texture.
@@ -23,6 +25,7 @@ pub struct PixelBuffer<T> {
     buffer: BufferViewAny,
     dimensions: Option<(u32, u32)>,
     format: Option<ClientFormat>,
+    fence: Cell<Option<Fence>>,
     marker: PhantomData<T>,
 }
 
@@ -34,6 +37,25 @@ impl<T> PixelBuffer<T> {
                                            false).unwrap().into(),
             dimensions: None,
             format: None,
+            fence: Cell::new(None),
+            marker: PhantomData,
+        }
+    }
+
+    /// Builds a new, empty pixel-unpack buffer.
+    ///
+    /// Unlike `new_empty`, which services the pack (video memory to RAM) direction, a buffer
+    /// built this way is meant to be filled from RAM with `write` and then handed to a
+    /// texture's upload function, so the driver can DMA the transfer asynchronously instead of
+    /// blocking on a client-side pointer. This enables double-buffered texture streaming, where
+    /// the CPU writes into one `PixelBuffer` while the GPU consumes another.
+    pub fn new_empty_unpack<F>(facade: &F, capacity: usize) -> PixelBuffer<T> where F: Facade {
+        PixelBuffer {
+            buffer: BufferView::<u8>::empty(facade, BufferType::PixelUnpackBuffer, capacity,
+                                           false).unwrap().into(),
+            dimensions: None,
+            format: None,
+            fence: Cell::new(None),
             marker: PhantomData,
         }
     }
@@ -42,6 +64,35 @@ impl<T> PixelBuffer<T> {
     pub fn get_size(&self) -> usize {
         self.buffer.get_size()
     }
+
+    /// Returns the `(width, height)` of the image this buffer last received, either through
+    /// `write` or through a readback into it, or `None` if it has received neither.
+    pub fn get_dimensions(&self) -> Option<(u32, u32)> {
+        self.dimensions
+    }
+}
+
+impl<T> PixelBuffer<T> where T: Texture2dDataSource {
+    /// Fills this unpack buffer with pixel data from RAM.
+    ///
+    /// The buffer must have been created with `new_empty_unpack`. Once written, pass it to a
+    /// texture's upload function (such as `Texture2d::upload_from_pixel_buffer`) to stream the
+    /// data straight from video memory instead of from a client-side pointer.
+    ///
+    /// ## Panic
+    ///
+    /// Panics if the buffer is too small to hold `data`.
+    pub fn write(&mut self, data: T) {
+        let raw = data.into_raw();
+
+        assert!(raw.data.len() <= self.get_size(),
+                "The pixel buffer is too small to hold this image");
+
+        self.buffer.upload(&raw.data);
+
+        self.dimensions = Some((raw.width, raw.height));
+        self.format = Some(raw.format);
+    }
 }
 
 impl<T> PixelBuffer<T> where T: Texture2dDataSink {
@@ -66,10 +117,17 @@ impl<T> PixelBuffer<T> where T: Texture2dDataSink {
     ///
     /// This operation is slow and should be done outside of the rendering loop.
     ///
+    /// If a readback was started with `begin_async_read` and its fence hasn't signaled yet,
+    /// this blocks until it does. Prefer `try_read` to avoid stalling the pipeline.
+    ///
     /// ## Panic
     ///
     /// Panics if the pixel buffer is empty.
     pub fn read_if_supported(&self) -> Option<T> {
+        if let Some(fence) = self.fence.take() {
+            fence.wait();
+        }
+
         let data = match unsafe { self.buffer.read_if_supported() } {
             Some(d) => d,
             None => return None
@@ -86,6 +144,78 @@ impl<T> PixelBuffer<T> where T: Texture2dDataSink {
 
         Some(Texture2dDataSink::from_raw(data))
     }
+
+    /// Marks the point at which the pending GPU write into this buffer (a `glReadPixels` or a
+    /// texture-to-buffer copy) was issued, and returns a handle to poll or wait on it.
+    ///
+    /// This lets an application kick off a readback one frame and collect the result a frame or
+    /// two later, via the returned handle's `is_ready`/`try_read`, without stalling the
+    /// pipeline the way `read_if_supported` does.
+    ///
+    /// Falls back to the synchronous behavior (the handle always reports ready) if GL 3.2 /
+    /// `GL_ARB_sync` isn't available.
+    pub fn begin_async_read<C>(&self, caps: &C) -> AsyncRead<T> where C: CapabilitiesSource {
+        self.fence.set(self.buffer.insert_fence_if_supported(caps));
+        AsyncRead { buffer: self }
+    }
+
+    /// Returns `true` if the fence armed by `begin_async_read` has signaled, meaning the data
+    /// can be read back without stalling the pipeline.
+    ///
+    /// Always returns `true` if no readback is in flight, either because `begin_async_read` was
+    /// never called or because fences aren't supported on this backend.
+    pub fn is_ready(&self) -> bool {
+        match self.fence.take() {
+            Some(fence) => {
+                let ready = fence.is_signaled();
+                if !ready {
+                    self.fence.set(Some(fence));
+                }
+                ready
+            },
+            None => true,
+        }
+    }
+
+    /// Returns the buffer's contents if the fence armed by `begin_async_read` has signaled,
+    /// without blocking. Returns `None` if it hasn't signaled yet.
+    pub fn try_read(&self) -> Option<T> {
+        if self.is_ready() {
+            self.read_if_supported()
+        } else {
+            None
+        }
+    }
+}
+
+/// A handle to an in-flight asynchronous readback of a `PixelBuffer`, returned by
+/// `PixelBuffer::begin_async_read`.
+pub struct AsyncRead<'a, T: 'a> {
+    buffer: &'a PixelBuffer<T>,
+}
+
+impl<'a, T> AsyncRead<'a, T> where T: Texture2dDataSink {
+    /// Returns `true` if the readback has completed and can be collected without blocking.
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        self.buffer.is_ready()
+    }
+
+    /// Returns the readback's result if it has completed, without blocking.
+    #[inline]
+    pub fn try_read(&self) -> Option<T> {
+        self.buffer.try_read()
+    }
+
+    /// Blocks until the readback has completed, then returns its result.
+    ///
+    /// ## Panic
+    ///
+    /// Panics if the pixel buffer is empty.
+    #[inline]
+    pub fn read(&self) -> T {
+        self.buffer.read_if_supported().unwrap()
+    }
 }
 
 // TODO: rework this
@@ -102,3 +232,16 @@ pub fn store_infos<T>(b: &mut PixelBuffer<T>, dimensions: (u32, u32), format: Cl
     b.dimensions = Some(dimensions);
     b.format = Some(format);
 }
+
+// TODO: remove this hack
+//
+// Binds `buffer` as the current `GL_PIXEL_UNPACK_BUFFER` for the duration of `f`, so that a
+// texture upload function called within `f` reads its pixel data from the buffer (letting the
+// driver DMA the transfer) instead of from a client-side pointer. This is the integration point
+// used by texture types to upload from a `PixelBuffer` built with `new_empty_unpack`.
+#[doc(hidden)]
+pub fn bind_unpack_buffer_if_supported<T, F, R>(buffer: &PixelBuffer<T>, f: F) -> R
+    where F: FnOnce() -> R
+{
+    buffer.buffer.bind_to_pixel_unpack(|| f())
+}
@@ -0,0 +1,126 @@
+/*!
+Textures are images that are stored in video memory.
+
+Textures can be used as a source of pixel data for fragment shaders, as render targets, or as
+the destination of a pixel transfer from a `PixelBuffer`.
+*/
+use std::borrow::Cow;
+use std::ptr;
+
+use context::CommandContext;
+
+use GlObject;
+use gl;
+
+use pixel_buffer::{PixelBuffer, bind_unpack_buffer_if_supported};
+
+/// Raw, uncompressed image data held by the client, used both to upload to a texture and to
+/// receive the result of a `PixelBuffer` readback.
+#[derive(Clone)]
+pub struct RawImage2d<'a> {
+    /// The raw pixel data, tightly packed according to `format`.
+    pub data: Cow<'a, [u8]>,
+    /// Width of the image, in pixels.
+    pub width: u32,
+    /// Height of the image, in pixels.
+    pub height: u32,
+    /// Layout of each pixel in `data`.
+    pub format: ClientFormat,
+}
+
+/// A type that can be built from pixel data read back from video memory.
+pub trait Texture2dDataSink {
+    /// Builds a new object from raw pixel data.
+    fn from_raw(data: RawImage2d) -> Self;
+}
+
+/// A type that can be turned into pixel data suitable for uploading to a texture.
+pub trait Texture2dDataSource {
+    /// Returns the raw pixel data to upload.
+    fn into_raw(self) -> RawImage2d<'static>;
+}
+
+/// Describes the layout of a single pixel of client-side image data.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClientFormat {
+    /// Each pixel is made of 1 byte: red.
+    U8,
+    /// Each pixel is made of 3 bytes: red, green, blue.
+    U8U8U8,
+    /// Each pixel is made of 4 bytes: red, green, blue, alpha.
+    U8U8U8U8,
+}
+
+impl ClientFormat {
+    /// Returns the number of bytes occupied by a single pixel in this format.
+    #[inline]
+    pub fn get_size(&self) -> usize {
+        match *self {
+            ClientFormat::U8 => 1,
+            ClientFormat::U8U8U8 => 3,
+            ClientFormat::U8U8U8U8 => 4,
+        }
+    }
+
+    fn to_gl_format(&self) -> gl::types::GLenum {
+        match *self {
+            ClientFormat::U8 => gl::RED,
+            ClientFormat::U8U8U8 => gl::RGB,
+            ClientFormat::U8U8U8U8 => gl::RGBA,
+        }
+    }
+}
+
+/// A two-dimensional texture stored in video memory.
+pub struct Texture2d {
+    texture: gl::types::GLuint,
+    width: u32,
+    height: u32,
+}
+
+impl GlObject for Texture2d {
+    type Id = gl::types::GLuint;
+
+    #[inline]
+    fn get_id(&self) -> gl::types::GLuint {
+        self.texture
+    }
+}
+
+impl Texture2d {
+    /// Uploads the content of `pixel_buffer` into this texture.
+    ///
+    /// If `pixel_buffer` was built with `PixelBuffer::new_empty_unpack` and filled with
+    /// `PixelBuffer::write`, it is bound as the current `GL_PIXEL_UNPACK_BUFFER` for the
+    /// duration of the call, so the driver reads the pixel data straight out of video memory
+    /// instead of from a client-side pointer. This is the integration point that makes the
+    /// double-buffered streaming described on `PixelBuffer::new_empty_unpack` actually work:
+    /// the CPU writes into one `PixelBuffer` while this call consumes another.
+    ///
+    /// `format` must match the layout `pixel_buffer` was written with.
+    ///
+    /// ## Panic
+    ///
+    /// Panics if `pixel_buffer` has no recorded dimensions (it has received neither a
+    /// `PixelBuffer::write` nor a readback), or if those dimensions don't match this texture's
+    /// own dimensions. Without this
+    /// check, a pixel buffer written with a smaller image would still be read for
+    /// `self.width * self.height` texels, running the unpack read off the end of the data the
+    /// buffer actually holds.
+    pub unsafe fn upload_from_pixel_buffer<T>(&self, ctxt: &mut CommandContext,
+                                               pixel_buffer: &PixelBuffer<T>, format: ClientFormat)
+    {
+        let dimensions = pixel_buffer.get_dimensions()
+                                      .expect("pixel_buffer has never been written to");
+        assert_eq!(dimensions, (self.width, self.height),
+                   "pixel_buffer's dimensions don't match this texture's dimensions");
+
+        ctxt.gl.BindTexture(gl::TEXTURE_2D, self.texture);
+
+        bind_unpack_buffer_if_supported(pixel_buffer, || {
+            ctxt.gl.TexSubImage2D(gl::TEXTURE_2D, 0, 0, 0, self.width as gl::types::GLsizei,
+                                   self.height as gl::types::GLsizei, format.to_gl_format(),
+                                   gl::UNSIGNED_BYTE, ptr::null());
+        });
+    }
+}